@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use libloading::{Library, Symbol};
+use once_cell::sync::Lazy;
+use tree_sitter::{Language, Parser as TSParser, Tree};
+
+/// A tree-sitter grammar loaded at runtime from a compiled shared object,
+/// as opposed to the grammars baked in at compile time by [`mk_langs!`].
+///
+/// The owning [`Library`] is kept alive alongside the [`Language`] it
+/// produced, since the `Language` borrows function pointers from it.
+pub struct DynLang {
+    name: String,
+    language: Language,
+    // Never read directly, but must outlive `language`.
+    _library: Library,
+}
+
+impl DynLang {
+    /// Loads the grammar shared object at `path` and resolves the
+    /// `tree_sitter_<name>` symbol it is expected to export.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the library cannot be opened or the expected
+    /// symbol is missing.
+    fn load(name: &str, path: &Path) -> Result<Self, DynLangError> {
+        // Safety: we trust the caller to point us at a genuine tree-sitter
+        // grammar shared object; the symbol below is resolved against the
+        // `tree_sitter_<name>() -> tree_sitter::Language` ABI that every
+        // generated grammar exposes.
+        let library = unsafe { Library::new(path) }
+            .map_err(|err| DynLangError::Load(path.to_path_buf(), err.to_string()))?;
+
+        let symbol_name = format!("tree_sitter_{name}");
+        let language = unsafe {
+            let constructor: Symbol<unsafe extern "C" fn() -> Language> = library
+                .get(symbol_name.as_bytes())
+                .map_err(|err| DynLangError::MissingSymbol(symbol_name.clone(), err.to_string()))?;
+            constructor()
+        };
+
+        Ok(DynLang {
+            name: name.to_string(),
+            language,
+            _library: library,
+        })
+    }
+
+    /// The name this grammar was registered under.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn parser(&self) -> Result<TSParser, DynLangError> {
+        let mut parser = TSParser::new();
+        parser
+            .set_language(self.language)
+            .map_err(|err| DynLangError::Incompatible(self.name.clone(), err.to_string()))?;
+        Ok(parser)
+    }
+}
+
+/// Errors produced while registering or driving a runtime-loaded grammar.
+#[derive(Debug, thiserror::Error)]
+pub enum DynLangError {
+    #[error("unable to load grammar library at {0:?}: {1}")]
+    Load(std::path::PathBuf, String),
+    #[error("symbol {0} not found in grammar library: {1}")]
+    MissingSymbol(String, String),
+    #[error("grammar {0} is incompatible with this tree-sitter runtime: {1}")]
+    Incompatible(String, String),
+    #[error("grammar {0} is not registered")]
+    NotRegistered(String),
+    #[error("{0} is not available for dynamically loaded grammars, which have no baked-in kind ids")]
+    Unsupported(&'static str),
+    #[error("failed to parse source with grammar {0}")]
+    ParseFailed(String),
+}
+
+static REGISTRY: Lazy<RwLock<HashMap<String, Arc<DynLang>>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Registers a runtime grammar under `name`, loading it from the compiled
+/// shared object at `path`.
+///
+/// The grammar is cached for the lifetime of the process: later calls for
+/// the same `name` are cheap lookups, and the underlying [`Library`] stays
+/// mapped for as long as the process runs.
+pub fn register_grammar(name: &str, path: &Path) -> Result<(), DynLangError> {
+    let mut registry = REGISTRY.write().unwrap();
+    if registry.contains_key(name) {
+        return Ok(());
+    }
+    let dyn_lang = DynLang::load(name, path)?;
+    registry.insert(name.to_string(), Arc::new(dyn_lang));
+    Ok(())
+}
+
+fn lookup(name: &str) -> Result<Arc<DynLang>, DynLangError> {
+    REGISTRY
+        .read()
+        .unwrap()
+        .get(name)
+        .cloned()
+        .ok_or_else(|| DynLangError::NotRegistered(name.to_string()))
+}
+
+fn parse(name: &str, source: &[u8]) -> Result<(Arc<DynLang>, Tree), DynLangError> {
+    let dyn_lang = lookup(name)?;
+    let mut parser = dyn_lang.parser()?;
+    let tree = parser
+        .parse(source, None)
+        .ok_or_else(|| DynLangError::ParseFailed(name.to_string()))?;
+    Ok((dyn_lang, tree))
+}
+
+fn walk_preorder(tree: &Tree, mut visit: impl FnMut(&tree_sitter::Node, usize)) {
+    let mut cursor = tree.walk();
+    let mut depth = 0;
+    let mut reachable = true;
+    while reachable {
+        visit(&cursor.node(), depth);
+        if cursor.goto_first_child() {
+            depth += 1;
+            continue;
+        }
+        reachable = loop {
+            if cursor.goto_next_sibling() {
+                break true;
+            }
+            if !cursor.goto_parent() {
+                break false;
+            }
+            depth -= 1;
+        };
+    }
+}
+
+/// Dumps the full AST of source code parsed with a runtime-loaded grammar
+/// previously registered via [`register_grammar`].
+///
+/// This is the `action_dyn` entry point: an AST dump needs only node kinds
+/// and byte ranges, which every grammar exposes uniformly, so it works the
+/// same way for runtime grammars as for the ones [`mk_langs!`] bakes in.
+/// Metrics that classify nodes by language-specific `kind_id` (function
+/// spaces, cyclomatic complexity, ...) aren't available this way; use
+/// [`crate::action`] with a compiled-in [`LANG`](crate::LANG) for those.
+pub fn action_dyn(name: &str, source: &[u8]) -> Result<String, DynLangError> {
+    let (_dyn_lang, tree) = parse(name, source)?;
+    let mut dump = String::new();
+    walk_preorder(&tree, |node, depth| {
+        dump.push_str(&format!(
+            "{}{} [{}, {})\n",
+            "  ".repeat(depth),
+            node.kind(),
+            node.start_byte(),
+            node.end_byte(),
+        ));
+    });
+    Ok(dump)
+}
+
+/// Returns the number of nodes of each kind in source code parsed with a
+/// runtime-loaded grammar.
+///
+/// Node counts, like the AST dump above, only need a node's own kind, not
+/// a per-language `kind_id` table, so they work the same way for runtime
+/// grammars as for compiled-in ones.
+pub fn count_nodes_dyn(name: &str, source: &[u8]) -> Result<HashMap<String, usize>, DynLangError> {
+    let (_dyn_lang, tree) = parse(name, source)?;
+    let mut counts = HashMap::new();
+    walk_preorder(&tree, |node, _depth| {
+        *counts.entry(node.kind().to_string()).or_insert(0) += 1;
+    });
+    Ok(counts)
+}
+
+/// Returns all function spaces data of source code parsed with a
+/// runtime-loaded grammar.
+///
+/// Unlike the AST dump, node counts, and operand/operator extraction
+/// in this module, partitioning a file into function/class/namespace spaces needs
+/// to recognize which node kinds open a new space, which is exactly the
+/// kind of per-language `kind_id` classification that [`mk_checker!`]
+/// bakes in at compile time for [`crate::get_function_spaces`]. A runtime
+/// grammar has no such table, so this always fails clearly rather than
+/// guessing at node kinds.
+pub fn get_function_spaces_dyn(name: &str, _source: &[u8]) -> Result<(), DynLangError> {
+    // Confirm the grammar is actually registered before reporting the
+    // metric itself as unsupported, so a typo'd name still gets
+    // `NotRegistered` rather than a misleading `Unsupported`.
+    lookup(name)?;
+    Err(DynLangError::Unsupported("get_function_spaces"))
+}
+
+/// Returns the operands and operators of source code parsed with a
+/// runtime-loaded grammar, as `(operators, operands)`.
+///
+/// Operand/operator extraction walks the tree by structural shape, named
+/// vs. unnamed leaf nodes, rather than by specific `kind_id`, so it works
+/// the same way here as it does for compiled-in grammars in [`get_ops`].
+///
+/// [`get_ops`]: crate::get_ops
+pub fn get_ops_dyn(name: &str, source: &[u8]) -> Result<(Vec<String>, Vec<String>), DynLangError> {
+    let (_dyn_lang, tree) = parse(name, source)?;
+    let mut operators = Vec::new();
+    let mut operands = Vec::new();
+    walk_preorder(&tree, |node, _depth| {
+        if node.child_count() != 0 {
+            return;
+        }
+        let text = node.utf8_text(source).unwrap_or_default().to_string();
+        if node.is_named() {
+            operands.push(text);
+        } else {
+            operators.push(text);
+        }
+    });
+    Ok((operators, operands))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walk_preorder_depth_tracks_tree_nesting_not_source_line() {
+        // A single-line source: every node sits on row 0, so indenting by
+        // row (the original bug) would flatten the whole dump. Indenting
+        // by cursor depth must still show the declaration's nesting.
+        let mut parser = TSParser::new();
+        parser.set_language(tree_sitter_mozcpp::language()).unwrap();
+        let tree = parser.parse("int a = 42;", None).unwrap();
+
+        let mut depths = Vec::new();
+        walk_preorder(&tree, |node, depth| {
+            depths.push((node.kind().to_string(), depth))
+        });
+
+        assert_eq!(depths[0].1, 0);
+        assert!(
+            depths.iter().any(|&(_, depth)| depth >= 2),
+            "expected at least one node nested two levels below the root, got {depths:?}"
+        );
+        assert!(
+            depths.iter().map(|&(_, depth)| depth).collect::<std::collections::HashSet<_>>().len() > 1,
+            "all nodes reported the same depth: {depths:?}"
+        );
+    }
+
+    #[test]
+    fn get_function_spaces_dyn_reports_not_registered_before_unsupported() {
+        let err = get_function_spaces_dyn("not_a_registered_grammar", b"int a = 42;")
+            .expect_err("an unregistered grammar must error");
+        assert!(matches!(err, DynLangError::NotRegistered(_)));
+    }
+}
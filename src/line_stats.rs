@@ -0,0 +1,288 @@
+use std::path::Path;
+
+use crate::node::Node;
+use crate::traits::TSLanguage;
+
+/// A breakdown of a space's physical source lines into code, comment, and
+/// blank lines, computed from the real parse tree rather than a regex.
+///
+/// This is distinct from the logical SLOC metrics: a line containing both
+/// code and a trailing comment counts as a code line here, and a line is
+/// only a comment line when it is wholly contained in one or more comment
+/// nodes. Lines belonging to a nested space (see [`SpaceLineStats`]) are
+/// not double-counted in an enclosing one.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineStats {
+    /// Number of lines containing at least one non-comment, non-blank byte.
+    pub code: usize,
+    /// Number of lines wholly contained in one or more comment nodes.
+    pub comment: usize,
+    /// Number of lines with no non-whitespace bytes.
+    pub blank: usize,
+}
+
+/// Implemented by each language's code type to recognize comment nodes, so
+/// [`get_line_stats`] can walk the tree the same way for every grammar.
+///
+/// [`mk_code!`] generates this from the comment-kind list declared per
+/// language alongside the extension and `Emacs`-mode lists in
+/// [`mk_langs!`].
+pub trait LineCommentChecker {
+    fn is_comment(node: &Node) -> bool;
+}
+
+/// Implemented by each language's code type to recognize the nodes
+/// `FuncSpace` partitions a file into (functions, classes, namespaces, ...),
+/// so [`get_line_stats`] can report the same per-space breakdown `FuncSpace`
+/// does instead of one flat whole-file count.
+///
+/// [`mk_code!`] generates this from the space-kind list declared per
+/// language alongside the comment-kind list in [`mk_langs!`].
+pub trait SpaceClassifier {
+    fn is_space(node: &Node) -> bool;
+}
+
+/// The [`LineStats`] of one space, nested the same way `FuncSpace` is: a
+/// line belongs to its innermost enclosing space only, so a function's
+/// lines aren't also counted in its parent's totals.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpaceLineStats {
+    /// The space's node kind (e.g. `function_item`), or `"<file>"` for the
+    /// space rooted at the whole file.
+    pub name: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub stats: LineStats,
+    pub spaces: Vec<SpaceLineStats>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum LineKind {
+    Blank,
+    Comment,
+    Code,
+}
+
+fn physical_line_count(source: &[u8]) -> usize {
+    let newlines = source.iter().filter(|&&b| b == b'\n').count();
+    // A trailing `\n` terminates the last physical line rather than
+    // starting a new (non-existent) one, so only count it as a line of
+    // its own when there are trailing bytes after the last `\n`.
+    if source.last() == Some(&b'\n') {
+        newlines
+    } else {
+        newlines + 1
+    }
+    .max(1)
+}
+
+/// Classifies every physical line of `source` by walking the leaves of the
+/// parse tree: a line touched only by comment-node leaves is a comment
+/// line, a line touched by at least one non-comment leaf is a code line,
+/// and an untouched line is blank.
+fn classify_lines<T: LineCommentChecker>(root: Node, line_count: usize) -> Vec<LineKind> {
+    let mut is_comment_line = vec![true; line_count];
+    let mut any_content = vec![false; line_count];
+
+    let mut cursor = root.object().walk();
+    loop {
+        let node = cursor.node();
+        if node.child_count() == 0 && node.end_byte() > node.start_byte() {
+            let comment = T::is_comment(&Node::new(node));
+            for row in node.start_position().row..=last_row(&node) {
+                any_content[row] = true;
+                if !comment {
+                    is_comment_line[row] = false;
+                }
+            }
+        }
+
+        if cursor.goto_first_child() {
+            continue;
+        }
+        loop {
+            if cursor.goto_next_sibling() {
+                break;
+            }
+            if !cursor.goto_parent() {
+                return (0..line_count)
+                    .map(|row| {
+                        if !any_content[row] {
+                            LineKind::Blank
+                        } else if is_comment_line[row] {
+                            LineKind::Comment
+                        } else {
+                            LineKind::Code
+                        }
+                    })
+                    .collect();
+            }
+        }
+    }
+}
+
+/// The last physical line `node` covers. A node ending at column 0 ends just
+/// after a `\n`, so the row its end position names holds none of its bytes.
+fn last_row(node: &tree_sitter::Node) -> usize {
+    let end = node.end_position();
+    if end.column == 0 && end.row > node.start_position().row {
+        end.row - 1
+    } else {
+        end.row
+    }
+}
+
+fn space_stats(lines: &[LineKind], start_line: usize, end_line: usize, owned_by_children: &[bool]) -> LineStats {
+    let mut stats = LineStats::default();
+    for row in start_line..=end_line {
+        if owned_by_children[row] {
+            continue;
+        }
+        match lines[row] {
+            LineKind::Blank => stats.blank += 1,
+            LineKind::Comment => stats.comment += 1,
+            LineKind::Code => stats.code += 1,
+        }
+    }
+    stats
+}
+
+/// Builds the [`SpaceLineStats`] for `node`, recursing into the nearest
+/// descendant nodes recognized by `T::is_space` to build nested spaces.
+fn build_space<T: SpaceClassifier>(
+    node: tree_sitter::Node,
+    name: String,
+    lines: &[LineKind],
+) -> SpaceLineStats {
+    let start_line = node.start_position().row;
+    let end_line = last_row(&node);
+
+    let mut spaces = Vec::new();
+    collect_nested_spaces::<T>(node, lines, &mut spaces);
+
+    let mut owned_by_children = vec![false; lines.len()];
+    for space in &spaces {
+        for row in space.start_line..=space.end_line {
+            owned_by_children[row] = true;
+        }
+    }
+
+    SpaceLineStats {
+        name,
+        start_line,
+        end_line,
+        stats: space_stats(lines, start_line, end_line, &owned_by_children),
+        spaces,
+    }
+}
+
+/// Finds the spaces nested directly inside `node`: descendants recognized
+/// by `T::is_space`, stopping the descent at the first one found along
+/// each path so spaces aren't reported twice (once for themselves, once as
+/// a space nested in themselves).
+fn collect_nested_spaces<T: SpaceClassifier>(
+    node: tree_sitter::Node,
+    lines: &[LineKind],
+    out: &mut Vec<SpaceLineStats>,
+) {
+    let mut cursor = node.walk();
+    if !cursor.goto_first_child() {
+        return;
+    }
+    loop {
+        let child = cursor.node();
+        if T::is_space(&Node::new(child)) {
+            out.push(build_space::<T>(child, child.kind().to_string(), lines));
+        } else {
+            collect_nested_spaces::<T>(child, lines, out);
+        }
+        if !cursor.goto_next_sibling() {
+            break;
+        }
+    }
+}
+
+/// Computes the per-space [`LineStats`] for an entire source file: a tree
+/// rooted at the whole file (named `"<file>"`), with one nested
+/// [`SpaceLineStats`] per function/class/namespace `FuncSpace` would also
+/// partition the file into, giving tokei-style comment-density figures at
+/// whatever granularity a caller needs instead of only a whole-file count.
+///
+/// # Examples
+///
+/// The crate-level `get_line_stats` picks the parser for a [`LANG`], builds
+/// it, and calls this function:
+///
+/// ```
+/// use std::path::PathBuf;
+///
+/// use rust_code_analysis::{get_line_stats, LANG};
+///
+/// let source_code = "// a comment\nint a = 42;\n\n";
+/// let language = LANG::Cpp;
+///
+/// let path = PathBuf::from("foo.c");
+/// let source_as_vec = source_code.as_bytes().to_vec();
+///
+/// get_line_stats(&language, source_as_vec, &path, None).unwrap();
+/// ```
+pub fn get_line_stats<T: TSLanguage + LineCommentChecker + SpaceClassifier>(
+    parser: &Parser<T>,
+    _path: &Path,
+) -> Option<SpaceLineStats> {
+    let source = parser.get_code();
+    let line_count = physical_line_count(source);
+    let root = parser.get_root();
+    let lines = classify_lines::<T>(root, line_count);
+    Some(build_space::<T>(
+        root.object(),
+        "<file>".to_string(),
+        &lines,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn trailing_newline_does_not_add_a_phantom_blank_line() {
+        let path = PathBuf::from("foo.c");
+        let source = b"// a comment\nint a = 42;\n\n".to_vec();
+        let parser = crate::CppParser::new(source, &path, None);
+
+        let stats = get_line_stats(&parser, &path).unwrap();
+
+        assert_eq!(
+            stats.stats,
+            LineStats {
+                code: 1,
+                comment: 1,
+                blank: 1,
+            }
+        );
+        assert!(stats.spaces.is_empty());
+    }
+
+    #[test]
+    fn nested_function_lines_are_not_double_counted_in_the_file_space() {
+        let path = PathBuf::from("foo.c");
+        // One comment line at file scope, then a function whose body is a
+        // single blank line.
+        let source = b"// top-level comment\nint f() {\n\n}\n".to_vec();
+        let parser = crate::CppParser::new(source, &path, None);
+
+        let file_stats = get_line_stats(&parser, &path).unwrap();
+
+        assert_eq!(file_stats.spaces.len(), 1);
+        let total_lines: usize = file_stats.stats.code
+            + file_stats.stats.comment
+            + file_stats.stats.blank
+            + file_stats.spaces[0].stats.code
+            + file_stats.spaces[0].stats.comment
+            + file_stats.spaces[0].stats.blank;
+        assert_eq!(total_lines, 4);
+    }
+}
@@ -12,18 +12,30 @@ macro_rules! mk_checker {
     };
 }
 
-macro_rules! mk_else_if {
-    ($if_type:ident) => {
+// Generates a predicate recognizing a node of kind `$kind` whose ancestor
+// chain, walked from its immediate parent upward, matches `$ancestor, ...`.
+// e.g. `mk_nesting_checker!(is_else_if, If, [If])` is the old `is_else_if`;
+// `mk_nesting_checker!(is_nested_catch, Catch, [Try])` recognizes a `Catch`
+// directly inside a `Try`.
+macro_rules! mk_nesting_checker {
+    ($name:ident, $kind:ident, [ $( $ancestor:ident ),+ ]) => {
         #[inline(always)]
-        fn is_else_if(node: &Node) -> bool {
-            if node.object().kind_id() != <Self as TSLanguage>::BaseLang::$if_type {
+        fn $name(node: &Node) -> bool {
+            if node.object().kind_id() != <Self as TSLanguage>::BaseLang::$kind {
                 return false;
             }
-            if let Some(parent) = node.object().parent() {
-                return node.object().kind_id() == <Self as TSLanguage>::BaseLang::$if_type
-                    && parent.kind_id() == <Self as TSLanguage>::BaseLang::$if_type;
-            }
-            false
+            let mut current = node.object();
+            $(
+                let parent = match current.parent() {
+                    Some(parent) => parent,
+                    None => return false,
+                };
+                if parent.kind_id() != <Self as TSLanguage>::BaseLang::$ancestor {
+                    return false;
+                }
+                current = parent;
+            )+
+            true
         }
     };
 }
@@ -190,6 +202,37 @@ macro_rules! mk_action {
                 )*
             }
         }
+
+        /// Returns the per-space comment/blank/code line breakdown of a
+        /// code: a tree rooted at the whole file, with one nested entry
+        /// per function/class/namespace space.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// use std::path::PathBuf;
+        ///
+        /// use rust_code_analysis::{get_line_stats, LANG};
+        ///
+        /// let source_code = "// a comment\nint a = 42;\n\n";
+        /// let language = LANG::Cpp;
+        ///
+        /// let path = PathBuf::from("foo.c");
+        /// let source_as_vec = source_code.as_bytes().to_vec();
+        ///
+        /// get_line_stats(&language, source_as_vec, &path, None).unwrap();
+        /// ```
+        #[inline(always)]
+        pub fn get_line_stats(lang: &LANG, source: Vec<u8>, path: &Path, pr: Option<Arc<PreprocResults>>) -> Option<SpaceLineStats> {
+            match lang {
+                $(
+                    LANG::$camel => {
+                        let parser = $parser::new(source, &path, pr);
+                        line_stats::get_line_stats(&parser, &path)
+                    },
+                )*
+            }
+        }
     };
 }
 
@@ -248,8 +291,109 @@ macro_rules! mk_emacs_mode {
     };
 }
 
+macro_rules! mk_shebang {
+    ( $( ($camel:ident, [ $( $interpreter:expr ),* ]) ),* ) => {
+        /// Detects the language associated to a file's shebang line.
+        ///
+        /// `first_line` is expected to be the first line of a file,
+        /// including the leading `#!` if it has one. The interpreter path
+        /// is reduced to its last path component, the `env INTERP` form is
+        /// unwrapped, and a trailing version suffix such as `3` or `2.7`
+        /// is stripped, so `#!/usr/bin/env python3` and `#!/usr/bin/perl`
+        /// both resolve the same way as a bare `python`/`perl` token would.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// use rust_code_analysis::get_from_shebang;
+        ///
+        /// let first_line = "#!/usr/bin/env python3";
+        ///
+        /// get_from_shebang(first_line).unwrap();
+        /// ```
+        pub fn get_from_shebang(first_line: &str) -> Option<LANG> {
+            let line = first_line.trim_start().strip_prefix("#!")?.trim();
+            if line.is_empty() {
+                return None;
+            }
+
+            let mut tokens = line.split_whitespace();
+            let mut interpreter = tokens.next()?;
+            // #!/usr/bin/env python3
+            if interpreter.rsplit('/').next() == Some("env") {
+                interpreter = tokens.next()?;
+            }
+
+            let name = interpreter.rsplit('/').next().unwrap_or(interpreter);
+            let name = name.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+
+            match name {
+                $(
+                    $(
+                        $interpreter => Some(LANG::$camel),
+                    )*
+                )*
+                _ => None,
+            }
+        }
+    };
+}
+
+macro_rules! mk_modeline {
+    () => {
+        /// Detects the language associated to a `vim`-style modeline.
+        ///
+        /// `vim` looks for modelines in the first and last few lines of a
+        /// file; this mirrors that by scanning every line given to it for
+        /// a `vim:`/`vi:`/`ex:` modeline carrying an `ft=`/`filetype=`
+        /// field, e.g. `// vim: set ft=rust:`.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// use rust_code_analysis::get_from_modeline;
+        ///
+        /// let line = "// vim: set ft=rust:";
+        ///
+        /// get_from_modeline(line).unwrap();
+        /// ```
+        pub fn get_from_modeline(line: &str) -> Option<LANG> {
+            // Real `vim` modeline keywords are only recognized at the start
+            // of the line or after whitespace/a comment lead-in, so a word
+            // that merely contains "ex:" (e.g. "index:", "complex:") isn't
+            // mistaken for one.
+            fn find_keyword(line: &str, keyword: &str) -> Option<usize> {
+                line.match_indices(keyword).find_map(|(i, _)| {
+                    let preceded_by_boundary = line[..i]
+                        .chars()
+                        .next_back()
+                        .map_or(true, |c| !c.is_alphanumeric());
+                    preceded_by_boundary.then_some(i + keyword.len())
+                })
+            }
+
+            let line = line.trim();
+            let modeline = find_keyword(line, "vim:")
+                .or_else(|| find_keyword(line, "vi:"))
+                .or_else(|| find_keyword(line, "ex:"))?;
+
+            // A modeline's `ft=`/`filetype=` value is, in practice, the
+            // same short name `Emacs` modes use, so we reuse that table
+            // rather than keeping a third one in sync.
+            line[modeline..]
+                .split(|c: char| c == ':' || c.is_whitespace())
+                .find_map(|field| {
+                    field
+                        .strip_prefix("ft=")
+                        .or_else(|| field.strip_prefix("filetype="))
+                })
+                .and_then(get_from_emacs_mode)
+        }
+    };
+}
+
 macro_rules! mk_code {
-    ( $( ($camel:ident, $code:ident, $parser:ident, $name:ident, $docname:expr) ),* ) => {
+    ( $( ($camel:ident, $code:ident, $parser:ident, $name:ident, $docname:expr, [ $( $comment_kind:ident ),* ], [ $( $space_kind:ident ),* ]) ),* ) => {
         $(
             pub struct $code { _guard: (), }
             impl CodeMetricsT for $code { }
@@ -270,6 +414,14 @@ macro_rules! mk_code {
                 }
             }
 
+            impl LineCommentChecker for $code {
+                mk_checker!(is_comment, $( $comment_kind ),*);
+            }
+
+            impl SpaceClassifier for $code {
+                mk_checker!(is_space, $( $space_kind ),*);
+            }
+
             #[doc = "The `"]
             #[doc = $docname]
             #[doc = "` language parser."]
@@ -279,13 +431,15 @@ macro_rules! mk_code {
 }
 
 macro_rules! mk_langs {
-    ( $( ($camel:ident, $description: expr, $display: expr, $code:ident, $parser:ident, $name:ident, [ $( $ext:ident ),* ], [ $( $emacs_mode:expr ),* ]) ),* ) => {
+    ( $( ($camel:ident, $description: expr, $display: expr, $code:ident, $parser:ident, $name:ident, [ $( $ext:ident ),* ], [ $( $emacs_mode:expr ),* ], [ $( $interpreter:expr ),* ], [ $( $comment_kind:ident ),* ], [ $( $space_kind:ident ),* ]) ),* ) => {
         mk_enum!($( $camel, $description ),*);
         mk_impl_lang!($( ($camel, $name, $display) ),*);
         mk_action!($( ($camel, $parser) ),*);
         mk_extensions!($( ($camel, [ $( $ext ),* ]) ),*);
         mk_emacs_mode!($( ($camel, [ $( $emacs_mode ),* ]) ),*);
-        mk_code!($( ($camel, $code, $parser, $name, stringify!($camel)) ),*);
+        mk_shebang!($( ($camel, [ $( $interpreter ),* ]) ),*);
+        mk_modeline!();
+        mk_code!($( ($camel, $code, $parser, $name, stringify!($camel), [ $( $comment_kind ),* ], [ $( $space_kind ),* ]) ),*);
     };
 }
 
@@ -328,3 +482,41 @@ macro_rules! check_metrics {
         }
     };
 }
+
+#[cfg(test)]
+mod shebang_and_modeline_tests {
+    use super::*;
+
+    #[test]
+    fn shebang_strips_env_indirection_and_version_suffix() {
+        assert_eq!(
+            get_from_shebang("#!/usr/bin/env python3"),
+            Some(LANG::Python)
+        );
+        assert_eq!(
+            get_from_shebang("#!/usr/bin/env node"),
+            Some(LANG::Javascript)
+        );
+        assert_eq!(get_from_shebang("int a = 42;"), None);
+    }
+
+    #[test]
+    fn modeline_reuses_emacs_mode_table() {
+        assert_eq!(
+            get_from_modeline("// vim: set ft=rust:"),
+            Some(LANG::Rust)
+        );
+        assert_eq!(
+            get_from_modeline("# vi: filetype=python"),
+            Some(LANG::Python)
+        );
+        assert_eq!(get_from_modeline("int a = 42;"), None);
+    }
+
+    #[test]
+    fn modeline_keyword_requires_a_word_boundary() {
+        // "index:" contains "ex:" as a substring but isn't a modeline.
+        assert_eq!(get_from_modeline("index: ft=rust"), None);
+        assert_eq!(get_from_modeline("complex: ft=rust"), None);
+    }
+}